@@ -9,7 +9,7 @@ pub struct AstType {
 }
 
 #[derive(Debug)]
-enum AstTypeRepr {
+pub(crate) enum AstTypeRepr {
     Sum(Vec<(String, AstTypeRepr)>),
     Product(Vec<(Option<String>, AstTypeRepr)>),
     Ctor(String, Vec<AstTypeRepr>),
@@ -23,6 +23,20 @@ impl AstType {
             repr: AstTypeRepr::from_rule_body(name, rule),
         }
     }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn repr(&self) -> &AstTypeRepr {
+        &self.repr
+    }
+}
+
+pub fn print_type_hierarchy(types: &[AstType]) {
+    for ty in types {
+        println!("{}", ty);
+    }
 }
 
 impl Display for AstType {
@@ -38,6 +52,14 @@ impl AstTypeRepr {
                 "list".to_owned(),
                 vec![AstTypeRepr::from_rule_body(name, &*content)],
             ),
+            RuleBody::Repeat1 { content } => AstTypeRepr::Ctor(
+                "list1".to_owned(),
+                vec![AstTypeRepr::from_rule_body(name, &*content)],
+            ),
+            RuleBody::Optional { content } => AstTypeRepr::Ctor(
+                "option".to_owned(),
+                vec![AstTypeRepr::from_rule_body(name, &*content)],
+            ),
             RuleBody::Choice { members } => AstTypeRepr::Sum(
                 members
                     .iter()
@@ -54,10 +76,26 @@ impl AstTypeRepr {
                 members
                     .iter()
                     .enumerate()
-                    .map(|(_, r)| (None, AstTypeRepr::from_rule_body(name, r)))
+                    .map(|(_, r)| match r {
+                        RuleBody::Field {
+                            name: field_name,
+                            content,
+                        } => (
+                            Some(field_name.clone()),
+                            AstTypeRepr::from_rule_body(name, content),
+                        ),
+                        _ => (None, AstTypeRepr::from_rule_body(name, r)),
+                    })
                     .collect(),
             ),
-            RuleBody::PrecLeft { content } => AstTypeRepr::from_rule_body(name, &*content),
+            RuleBody::PrecLeft { content } | RuleBody::PrecRight { content } => {
+                AstTypeRepr::from_rule_body(name, &*content)
+            }
+            RuleBody::Token { content } | RuleBody::ImmediateToken { content } => {
+                AstTypeRepr::from_rule_body(name, &*content)
+            }
+            RuleBody::Alias { content, .. } => AstTypeRepr::from_rule_body(name, &*content),
+            RuleBody::Field { content, .. } => AstTypeRepr::from_rule_body(name, &*content),
             RuleBody::Symbol { name } => AstTypeRepr::Name(name.to_owned()),
             RuleBody::String { .. } | RuleBody::Pattern { .. } => {
                 AstTypeRepr::Name("string".to_owned())
@@ -0,0 +1,632 @@
+use std::collections::HashMap;
+
+use crate::ast_types::{AstType, AstTypeRepr};
+use crate::grammar::RuleBody;
+use crate::type_gen::ParserDef;
+
+pub trait Backend {
+    fn emit(&self, types: &[AstType], groups: &[Vec<String>]) -> String;
+
+    fn emit_parsers(&self, _defs: &[ParserDef], _groups: &[Vec<String>]) -> String {
+        String::new()
+    }
+}
+
+fn group_of(groups: &[Vec<String>]) -> HashMap<&str, usize> {
+    groups
+        .iter()
+        .enumerate()
+        .flat_map(|(i, g)| g.iter().map(move |n| (n.as_str(), i)))
+        .collect()
+}
+
+pub struct RustBackend;
+
+impl RustBackend {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn render_type(
+        &self,
+        repr: &AstTypeRepr,
+        own_group: Option<usize>,
+        group_of: &HashMap<&str, usize>,
+    ) -> String {
+        match repr {
+            AstTypeRepr::Name(name) => {
+                let rendered = rust_type_name(name);
+                if own_group.is_some() && group_of.get(name.as_str()).copied() == own_group {
+                    format!("Box<{}>", rendered)
+                } else {
+                    rendered
+                }
+            }
+            AstTypeRepr::Ctor(ctor, args) if ctor == "list" => {
+                format!("Vec<{}>", self.render_type(&args[0], None, group_of))
+            }
+            // REPEAT1 is non-empty, unlike REPEAT's plain Vec<T> — model it as the
+            // first element plus the (possibly empty) rest, so callers can't lose
+            // that guarantee by matching on an empty Vec.
+            AstTypeRepr::Ctor(ctor, args) if ctor == "list1" => {
+                let elem = self.render_type(&args[0], None, group_of);
+                format!("({}, Vec<{}>)", elem, elem)
+            }
+            AstTypeRepr::Ctor(ctor, args) if ctor == "option" => {
+                format!("Option<{}>", self.render_type(&args[0], own_group, group_of))
+            }
+            AstTypeRepr::Ctor(ctor, args) => format!(
+                "{}<{}>",
+                pascal_case(ctor),
+                args.iter()
+                    .map(|a| self.render_type(a, own_group, group_of))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            AstTypeRepr::Product(members) => format!(
+                "({})",
+                members
+                    .iter()
+                    .map(|(_, t)| self.render_type(t, own_group, group_of))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            AstTypeRepr::Sum(_) => panic!(
+                "nested choice was not hoisted into its own named type before codegen"
+            ),
+        }
+    }
+
+    fn emit_sum(
+        &self,
+        name: &str,
+        cases: &[(String, AstTypeRepr)],
+        own_group: Option<usize>,
+        group_of: &HashMap<&str, usize>,
+    ) -> String {
+        let variants = cases
+            .iter()
+            .enumerate()
+            .map(|(i, (_, repr))| {
+                format!(
+                    "    {}{}({}),",
+                    pascal_case(name),
+                    i,
+                    self.render_type(repr, own_group, group_of)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "#[derive(Debug, Clone)]\npub enum {} {{\n{}\n}}",
+            pascal_case(name),
+            variants
+        )
+    }
+
+    fn emit_product(
+        &self,
+        name: &str,
+        members: &[(Option<String>, AstTypeRepr)],
+        own_group: Option<usize>,
+        group_of: &HashMap<&str, usize>,
+    ) -> String {
+        let fields = members
+            .iter()
+            .enumerate()
+            .map(|(i, (label, repr))| {
+                let field_name = label.clone().unwrap_or_else(|| format!("field_{}", i));
+                format!(
+                    "    pub {}: {},",
+                    field_name,
+                    self.render_type(repr, own_group, group_of)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "#[derive(Debug, Clone)]\npub struct {} {{\n{}\n}}",
+            pascal_case(name),
+            fields
+        )
+    }
+}
+
+impl Backend for RustBackend {
+    fn emit(&self, types: &[AstType], groups: &[Vec<String>]) -> String {
+        let group_of = group_of(groups);
+        types
+            .iter()
+            .map(|ty| {
+                let own_group = group_of.get(ty.name()).copied();
+                match ty.repr() {
+                    AstTypeRepr::Sum(cases) => self.emit_sum(ty.name(), cases, own_group, &group_of),
+                    AstTypeRepr::Product(members) => {
+                        self.emit_product(ty.name(), members, own_group, &group_of)
+                    }
+                    repr => format!(
+                        "pub type {} = {};",
+                        pascal_case(ty.name()),
+                        self.render_type(repr, own_group, &group_of)
+                    ),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn emit_parsers(&self, defs: &[ParserDef], groups: &[Vec<String>]) -> String {
+        let group_of = group_of(groups);
+        defs.iter()
+            .map(|def| {
+                let own_group = group_of.get(def.name.as_str()).copied();
+                self.emit_parser(&def.name, &def.body, own_group, &group_of)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+impl RustBackend {
+    fn emit_parser(
+        &self,
+        name: &str,
+        body: &RuleBody,
+        own_group: Option<usize>,
+        group_of: &HashMap<&str, usize>,
+    ) -> String {
+        format!(
+            "impl {} {{\n    pub fn from_node(node: tree_sitter::Node, source: &[u8]) -> Self {{\n{}\n    }}\n}}",
+            pascal_case(name),
+            indent(&self.render_ctor(name, body, own_group, group_of), 8)
+        )
+    }
+
+    fn render_ctor(
+        &self,
+        name: &str,
+        body: &RuleBody,
+        own_group: Option<usize>,
+        group_of: &HashMap<&str, usize>,
+    ) -> String {
+        match body {
+            RuleBody::Seq { members } => {
+                let elems = self.render_seq_elements(members, "node", own_group, group_of);
+                let fields = members
+                    .iter()
+                    .zip(elems)
+                    .enumerate()
+                    .map(|(i, (m, expr))| match m {
+                        RuleBody::Field { name: field_name, .. } => format!("{}: {}", field_name, expr),
+                        _ => format!("field_{}: {}", i, expr),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",\n");
+                format!("Self {{\n{}\n}}", indent(&fields, 4))
+            }
+            RuleBody::Choice { members } => {
+                // Members with a concrete discriminating kind must come before any
+                // wildcard fallback arm, or the wildcard would shadow them.
+                let mut indexed: Vec<(usize, &RuleBody)> = members.iter().enumerate().collect();
+                indexed.sort_by_key(|(_, m)| node_kinds(m).is_empty());
+                let arms = indexed
+                    .iter()
+                    .map(|(i, m)| {
+                        format!(
+                            "{} => {}::{}{}({}),",
+                            choice_member_pattern(m),
+                            pascal_case(name),
+                            pascal_case(name),
+                            i,
+                            self.render_child(m, "node", own_group, group_of)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                format!(
+                    "match node.kind() {{\n{}\n    _ => panic!(\"unexpected node kind: {{}}\", node.kind()),\n}}",
+                    indent(&arms, 4)
+                )
+            }
+            _ => self.render_child(body, "node", own_group, group_of),
+        }
+    }
+
+    // `node_expr` must already denote the node that directly represents `body`
+    // (e.g. the parent of a Seq for its own Repeat/Optional members, or a child
+    // already located via `locate_seq_member` for everything else).
+    fn render_child(
+        &self,
+        body: &RuleBody,
+        node_expr: &str,
+        own_group: Option<usize>,
+        group_of: &HashMap<&str, usize>,
+    ) -> String {
+        match body {
+            RuleBody::Symbol { name } => {
+                let ctor = format!("{}::from_node({}, source)", pascal_case(name), node_expr);
+                if own_group.is_some() && group_of.get(name.as_str()).copied() == own_group {
+                    format!("Box::new({})", ctor)
+                } else {
+                    ctor
+                }
+            }
+            RuleBody::String { .. } | RuleBody::Pattern { .. } => {
+                format!("{}.utf8_text(source).unwrap().to_owned()", node_expr)
+            }
+            // A Seq nested under a Choice alternative (or anywhere else besides a
+            // rule's own top-level body) has no named type of its own — it renders
+            // as the same anonymous tuple `ast_types::AstTypeRepr::Product` gives it.
+            RuleBody::Seq { members } => {
+                let elems = self.render_seq_elements(members, node_expr, own_group, group_of);
+                format!("({})", elems.join(", "))
+            }
+            RuleBody::Repeat { content } => self.render_repeat(content, node_expr, group_of),
+            RuleBody::Repeat1 { content } => self.render_repeat1(content, node_expr, group_of),
+            RuleBody::Optional { content } => {
+                self.render_optional(content, node_expr, own_group, group_of)
+            }
+            RuleBody::PrecLeft { content }
+            | RuleBody::PrecRight { content }
+            | RuleBody::Token { content }
+            | RuleBody::ImmediateToken { content }
+            | RuleBody::Field { content, .. } => {
+                self.render_child(content, node_expr, own_group, group_of)
+            }
+            RuleBody::Alias { content, .. } => {
+                self.render_child(content, node_expr, own_group, group_of)
+            }
+            RuleBody::Choice { .. } => {
+                unreachable!("nested Choice is always hoisted into its own Symbol before codegen")
+            }
+        }
+    }
+
+    // Renders each Seq member's extraction expression in order, sharing the
+    // same kind-skip bookkeeping `render_ctor`'s top-level Seq arm uses, so a
+    // nested Seq (e.g. a Choice alternative) locates children the same way a
+    // rule's own top-level Seq does.
+    fn render_seq_elements(
+        &self,
+        members: &[RuleBody],
+        node_expr: &str,
+        own_group: Option<usize>,
+        group_of: &HashMap<&str, usize>,
+    ) -> Vec<String> {
+        let mut located_kinds: Vec<Vec<String>> = Vec::new();
+        members
+            .iter()
+            .map(|m| match m {
+                RuleBody::Field { name: field_name, content } => self.render_child(
+                    content,
+                    &format!("{}.child_by_field_name(\"{}\").unwrap()", node_expr, field_name),
+                    own_group,
+                    group_of,
+                ),
+                RuleBody::Repeat { .. } | RuleBody::Repeat1 { .. } | RuleBody::Optional { .. } => {
+                    self.render_child(m, node_expr, own_group, group_of)
+                }
+                _ => {
+                    // Two Seq members can share a node kind (e.g. `seq($.id, "+", $.id)`),
+                    // so skip as many earlier matches as this member's kinds have already
+                    // been claimed by, rather than always taking the first match.
+                    let kinds = node_kinds(m);
+                    let skip = located_kinds
+                        .iter()
+                        .filter(|earlier| earlier.iter().any(|k| kinds.contains(k)))
+                        .count();
+                    let located = self.locate_seq_member(m, node_expr, skip);
+                    located_kinds.push(kinds);
+                    self.render_child(m, &located, own_group, group_of)
+                }
+            })
+            .collect()
+    }
+
+    // Locates the child of `parent_expr` that a non-Field, non-Repeat, non-Optional
+    // Seq member refers to. tree-sitter only preserves a member's relative order
+    // within its parent, not a fixed index, once literals/repeats/optionals with a
+    // variable number of children are interspersed, so members are found by node
+    // kind instead of by position. `skip` is the number of earlier Seq members with
+    // an overlapping kind, so members sharing a kind each resolve to a distinct child.
+    fn locate_seq_member(&self, member: &RuleBody, parent_expr: &str, skip: usize) -> String {
+        format!(
+            "{}.children(&mut {}.walk()).filter(|c| {}).nth({}).unwrap()",
+            parent_expr,
+            parent_expr,
+            kind_predicate(&node_kinds(member), "c"),
+            skip
+        )
+    }
+
+    // tree-sitter flattens `repeat(seq(...))` into the member nodes back-to-back
+    // (there is no grouping node per repetition), so a Seq content is gathered by
+    // chunking the filtered children into groups of `members.len()` instead of
+    // mapping node-for-node like every other REPEAT content does.
+    fn render_repeat(&self, content: &RuleBody, node_expr: &str, group_of: &HashMap<&str, usize>) -> String {
+        match content {
+            RuleBody::Seq { members } => {
+                let elems = members
+                    .iter()
+                    .enumerate()
+                    .map(|(i, m)| self.render_child(m, &format!("chunk[{}]", i), None, group_of))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "{}.children(&mut {}.walk()).filter(|c| {}).collect::<Vec<_>>().chunks({}).map(|chunk| ({})).collect()",
+                    node_expr,
+                    node_expr,
+                    kind_predicate(&node_kinds(content), "c"),
+                    members.len(),
+                    elems
+                )
+            }
+            _ => format!(
+                "{}.children(&mut {}.walk()).filter(|c| {}).map(|c| {}).collect()",
+                node_expr,
+                node_expr,
+                kind_predicate(&node_kinds(content), "c"),
+                self.render_child(content, "c", None, group_of)
+            ),
+        }
+    }
+
+    // Same flattening caveat as `render_repeat`, but REPEAT1 is non-empty: the
+    // first match is split out as the head, the rest collected as the tail Vec,
+    // matching the `(T, Vec<T>)` type `render_type` gives "list1".
+    fn render_repeat1(&self, content: &RuleBody, node_expr: &str, group_of: &HashMap<&str, usize>) -> String {
+        match content {
+            RuleBody::Seq { members } => {
+                let n = members.len();
+                let head = members
+                    .iter()
+                    .enumerate()
+                    .map(|(i, m)| self.render_child(m, &format!("matched[{}]", i), None, group_of))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let rest = members
+                    .iter()
+                    .enumerate()
+                    .map(|(i, m)| self.render_child(m, &format!("chunk[{}]", i), None, group_of))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "{{ let matched: Vec<_> = {}.children(&mut {}.walk()).filter(|c| {}).collect(); (({}), matched[{}..].chunks({}).map(|chunk| ({})).collect()) }}",
+                    node_expr,
+                    node_expr,
+                    kind_predicate(&node_kinds(content), "c"),
+                    head,
+                    n,
+                    n,
+                    rest
+                )
+            }
+            _ => format!(
+                "{{ let matched: Vec<_> = {}.children(&mut {}.walk()).filter(|c| {}).collect(); ({}, matched[1..].iter().copied().map(|c| {}).collect()) }}",
+                node_expr,
+                node_expr,
+                kind_predicate(&node_kinds(content), "c"),
+                self.render_child(content, "matched[0]", None, group_of),
+                self.render_child(content, "c", None, group_of)
+            ),
+        }
+    }
+
+    // OPTIONAL wrapping a Seq is likewise flattened by tree-sitter, so matching
+    // children are gathered once and the tuple is only built if all of them showed up.
+    fn render_optional(
+        &self,
+        content: &RuleBody,
+        node_expr: &str,
+        own_group: Option<usize>,
+        group_of: &HashMap<&str, usize>,
+    ) -> String {
+        match content {
+            RuleBody::Seq { members } => {
+                let n = members.len();
+                let elems = members
+                    .iter()
+                    .enumerate()
+                    .map(|(i, m)| self.render_child(m, &format!("matched[{}]", i), own_group, group_of))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "{{ let matched: Vec<_> = {}.children(&mut {}.walk()).filter(|c| {}).collect(); if matched.len() >= {} {{ Some(({})) }} else {{ None }} }}",
+                    node_expr,
+                    node_expr,
+                    kind_predicate(&node_kinds(content), "c"),
+                    n,
+                    elems
+                )
+            }
+            _ => format!(
+                "{}.children(&mut {}.walk()).find(|c| {}).map(|c| {})",
+                node_expr,
+                node_expr,
+                kind_predicate(&node_kinds(content), "c"),
+                self.render_child(content, "c", own_group, group_of)
+            ),
+        }
+    }
+}
+
+// The possible `Node::kind()` values that could match `body` at parse time.
+// Anonymous (unnamed) tokens report their own literal text as their kind.
+fn node_kinds(body: &RuleBody) -> Vec<String> {
+    match body {
+        RuleBody::Symbol { name } => vec![name.clone()],
+        RuleBody::String { value } | RuleBody::Pattern { value } => vec![value.clone()],
+        RuleBody::Alias { value, .. } => vec![value.clone()],
+        RuleBody::Choice { members } => members.iter().flat_map(node_kinds).collect(),
+        RuleBody::PrecLeft { content }
+        | RuleBody::PrecRight { content }
+        | RuleBody::Token { content }
+        | RuleBody::ImmediateToken { content }
+        | RuleBody::Field { content, .. }
+        | RuleBody::Repeat { content }
+        | RuleBody::Repeat1 { content }
+        | RuleBody::Optional { content } => node_kinds(content),
+        // A Seq has no kind of its own, but the union of its members' kinds is
+        // enough to tell it apart from sibling Choice alternatives or to filter
+        // its matching children out of a flattened REPEAT/OPTIONAL parent.
+        RuleBody::Seq { members } => {
+            let mut kinds = Vec::new();
+            for k in members.iter().flat_map(node_kinds) {
+                if !kinds.contains(&k) {
+                    kinds.push(k);
+                }
+            }
+            kinds
+        }
+    }
+}
+
+fn kind_predicate(kinds: &[String], var: &str) -> String {
+    if kinds.is_empty() {
+        "true".to_owned()
+    } else {
+        kinds
+            .iter()
+            .map(|k| format!("{}.kind() == {:?}", var, k))
+            .collect::<Vec<_>>()
+            .join(" || ")
+    }
+}
+
+fn choice_member_pattern(body: &RuleBody) -> String {
+    let kinds = node_kinds(body);
+    if kinds.is_empty() {
+        "_".to_owned()
+    } else {
+        kinds
+            .iter()
+            .map(|k| format!("{:?}", k))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+fn indent(s: &str, width: usize) -> String {
+    let pad = " ".repeat(width);
+    s.lines()
+        .map(|l| format!("{}{}", pad, l))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn pascal_case(s: &str) -> String {
+    s.split(|c: char| c == '_' || c == '-')
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut cs = w.chars();
+            match cs.next() {
+                Some(first) => first.to_uppercase().collect::<String>() + cs.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+fn rust_type_name(name: &str) -> String {
+    if name == "string" {
+        "String".to_owned()
+    } else {
+        pascal_case(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grammar::Grammar;
+    use crate::type_gen::TypeGenerator;
+
+    #[test]
+    fn seq_nested_in_choice_extracts_structurally_instead_of_raw_text() {
+        // expr: choice(seq($.expr, "+", $.expr), $.identifier)
+        let expr = RuleBody::Choice {
+            members: vec![
+                RuleBody::Seq {
+                    members: vec![
+                        RuleBody::Symbol { name: "expr".to_owned() },
+                        RuleBody::String { value: "+".to_owned() },
+                        RuleBody::Symbol { name: "expr".to_owned() },
+                    ],
+                },
+                RuleBody::Symbol { name: "identifier".to_owned() },
+            ],
+        };
+        let defs = vec![ParserDef { name: "expr".to_owned(), body: expr }];
+        let groups = vec![vec!["expr".to_owned()]];
+        let code = RustBackend::new().emit_parsers(&defs, &groups);
+
+        assert!(code.contains(concat!(
+            "\"expr\" | \"+\" => Expr::Expr0((",
+            "Box::new(Expr::from_node(node.children(&mut node.walk()).filter(|c| c.kind() == \"expr\").nth(0).unwrap(), source)), ",
+            "node.children(&mut node.walk()).filter(|c| c.kind() == \"+\").nth(0).unwrap().utf8_text(source).unwrap().to_owned(), ",
+            "Box::new(Expr::from_node(node.children(&mut node.walk()).filter(|c| c.kind() == \"expr\").nth(1).unwrap(), source)))),"
+        )));
+        assert!(code.contains("\"identifier\" => Expr::Expr1(Identifier::from_node(node, source)),"));
+        assert_eq!(code.matches("_ =>").count(), 1);
+    }
+
+    #[test]
+    fn repeat_of_seq_chunks_flattened_children_instead_of_filtering_every_sibling() {
+        // id_list: repeat(seq($.identifier, ","))
+        let id_list = RuleBody::Repeat {
+            content: Box::new(RuleBody::Seq {
+                members: vec![
+                    RuleBody::Symbol { name: "identifier".to_owned() },
+                    RuleBody::String { value: ",".to_owned() },
+                ],
+            }),
+        };
+        let defs = vec![ParserDef { name: "id_list".to_owned(), body: id_list }];
+        let code = RustBackend::new().emit_parsers(&defs, &[]);
+
+        assert!(code.contains(concat!(
+            "node.children(&mut node.walk()).filter(|c| c.kind() == \"identifier\" || c.kind() == \",\")",
+            ".collect::<Vec<_>>().chunks(2).map(|chunk| ",
+            "(Identifier::from_node(chunk[0], source), chunk[1].utf8_text(source).unwrap().to_owned())).collect()"
+        )));
+    }
+
+    #[test]
+    fn renamed_alias_in_a_cycle_boxes_through_its_display_name() {
+        // sum_expr: alias(choice(seq($.sum_expr, "+", $.sum_expr), $.number), "expr")
+        let sum_expr = RuleBody::Alias {
+            content: Box::new(RuleBody::Choice {
+                members: vec![
+                    RuleBody::Seq {
+                        members: vec![
+                            RuleBody::Symbol { name: "sum_expr".to_owned() },
+                            RuleBody::String { value: "+".to_owned() },
+                            RuleBody::Symbol { name: "sum_expr".to_owned() },
+                        ],
+                    },
+                    RuleBody::Symbol { name: "number".to_owned() },
+                ],
+            }),
+            named: true,
+            value: "expr".to_owned(),
+        };
+        let number = RuleBody::Pattern { value: "[0-9]+".to_owned() };
+        let g = Grammar::from_rules(vec![("sum_expr", sum_expr), ("number", number)]);
+
+        let mut gen = TypeGenerator::new(&g);
+        for rule in g.get_rules() {
+            gen.add_rule(rule);
+        }
+        let result = gen.gen();
+        let backend = RustBackend::new();
+        let types_code = backend.emit(&result.typedefs, &result.groups);
+        let parsers_code = backend.emit_parsers(&result.functiondefs, &result.groups);
+
+        // The ALIAS's display name ("expr"), not the hoisted sub-rule's own name
+        // ("sum_expr_0"), is what the rest of the cycle boxes against.
+        assert!(types_code.contains("pub type Expr = Box<SumExpr0>;"));
+        assert!(types_code.contains("Box<Expr>"));
+        assert!(parsers_code.contains("Box::new(SumExpr0::from_node(node, source))"));
+        assert!(parsers_code.contains("\"expr\" | \"+\" =>"));
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use regex::Regex;
+
+use crate::grammar::{Grammar, RuleBody};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum Symbol {
+    NonTerminal(String),
+    Literal(String),
+    // Holds the raw `PATTERN` regex source; the compiled `Regex` lives in
+    // `CompiledGrammar::patterns` since `Regex` isn't `Eq`/`Hash`.
+    Pattern(String),
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct EarleyState {
+    lhs: String,
+    alt: usize,
+    dot: usize,
+    origin: usize,
+}
+
+pub struct ValidationReport {
+    pub accepted: bool,
+    pub reachable: HashSet<String>,
+    pub unused: Vec<String>,
+    pub nullable: HashSet<String>,
+    pub ambiguous: Vec<String>,
+}
+
+struct CompiledGrammar {
+    start: String,
+    productions: HashMap<String, Vec<Vec<Symbol>>>,
+    patterns: HashMap<String, Regex>,
+}
+
+impl CompiledGrammar {
+    fn compile(g: &Grammar) -> Self {
+        let mut productions = HashMap::new();
+        let mut anon_count = 0;
+        let mut start = None;
+        for rule in g.get_rules() {
+            if rule.is_extra {
+                continue;
+            }
+            if start.is_none() {
+                start = Some(rule.name.clone());
+            }
+            let alts = flatten_alts(&rule.body, &mut productions, &mut anon_count);
+            productions
+                .entry(rule.name.clone())
+                .or_insert_with(Vec::new)
+                .extend(alts);
+        }
+        let patterns = productions
+            .values()
+            .flatten()
+            .flatten()
+            .filter_map(|sym| match sym {
+                Symbol::Pattern(p) => Some(p.clone()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|p| {
+                let anchored = Regex::new(&format!("^(?:{})", p))
+                    .unwrap_or_else(|e| panic!("invalid PATTERN regex {:?}: {}", p, e));
+                (p, anchored)
+            })
+            .collect();
+        Self {
+            start: start.unwrap_or_default(),
+            productions,
+            patterns,
+        }
+    }
+
+    fn reachable(&self) -> HashSet<String> {
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(self.start.clone());
+        seen.insert(self.start.clone());
+        while let Some(name) = queue.pop_front() {
+            for alt in self.productions.get(&name).into_iter().flatten() {
+                for sym in alt {
+                    if let Symbol::NonTerminal(next) = sym {
+                        if seen.insert(next.clone()) {
+                            queue.push_back(next.clone());
+                        }
+                    }
+                }
+            }
+        }
+        seen
+    }
+
+    fn nullable(&self) -> HashSet<String> {
+        let mut nullable = HashSet::new();
+        loop {
+            let mut changed = false;
+            for (name, alts) in &self.productions {
+                if nullable.contains(name) {
+                    continue;
+                }
+                let is_nullable = alts.iter().any(|alt| {
+                    alt.iter().all(|sym| match sym {
+                        Symbol::NonTerminal(n) => nullable.contains(n),
+                        Symbol::Literal(_) | Symbol::Pattern(_) => false,
+                    })
+                });
+                if is_nullable {
+                    nullable.insert(name.clone());
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        nullable
+    }
+
+    fn recognize(&self, input: &str) -> (bool, HashSet<String>) {
+        let n = input.len();
+        let mut chart: Vec<Vec<EarleyState>> = vec![Vec::new(); n + 1];
+        let mut seen: Vec<HashSet<EarleyState>> = vec![HashSet::new(); n + 1];
+        let mut derivations: HashMap<(String, usize, usize), HashSet<usize>> = HashMap::new();
+        let mut ambiguous = HashSet::new();
+
+        for alt in 0..self.productions.get(&self.start).map(Vec::len).unwrap_or(0) {
+            let state = EarleyState {
+                lhs: self.start.clone(),
+                alt,
+                dot: 0,
+                origin: 0,
+            };
+            if seen[0].insert(state.clone()) {
+                chart[0].push(state);
+            }
+        }
+
+        for i in 0..=n {
+            let mut idx = 0;
+            while idx < chart[i].len() {
+                let state = chart[i][idx].clone();
+                let prod = &self.productions[&state.lhs][state.alt];
+                if state.dot == prod.len() {
+                    let key = (state.lhs.clone(), state.origin, i);
+                    let distinct = derivations.entry(key.clone()).or_default();
+                    distinct.insert(state.alt);
+                    if distinct.len() > 1 {
+                        ambiguous.insert(state.lhs.clone());
+                    }
+                    let waiting = chart[state.origin].clone();
+                    for w in waiting {
+                        let wprod = &self.productions[&w.lhs][w.alt];
+                        if w.dot < wprod.len() && wprod[w.dot] == Symbol::NonTerminal(state.lhs.clone()) {
+                            let advanced = EarleyState {
+                                lhs: w.lhs.clone(),
+                                alt: w.alt,
+                                dot: w.dot + 1,
+                                origin: w.origin,
+                            };
+                            if seen[i].insert(advanced.clone()) {
+                                chart[i].push(advanced);
+                            }
+                        }
+                    }
+                } else {
+                    match &prod[state.dot] {
+                        Symbol::NonTerminal(nt) => {
+                            for alt in 0..self.productions.get(nt).map(Vec::len).unwrap_or(0) {
+                                let predicted = EarleyState {
+                                    lhs: nt.clone(),
+                                    alt,
+                                    dot: 0,
+                                    origin: i,
+                                };
+                                if seen[i].insert(predicted.clone()) {
+                                    chart[i].push(predicted);
+                                }
+                            }
+                        }
+                        Symbol::Literal(s) => {
+                            if input[i..].starts_with(s.as_str()) && !s.is_empty() {
+                                let j = i + s.len();
+                                let advanced = EarleyState {
+                                    lhs: state.lhs.clone(),
+                                    alt: state.alt,
+                                    dot: state.dot + 1,
+                                    origin: state.origin,
+                                };
+                                if seen[j].insert(advanced.clone()) {
+                                    chart[j].push(advanced);
+                                }
+                            }
+                        }
+                        Symbol::Pattern(p) => {
+                            let m = self.patterns[p].find(&input[i..]).filter(|m| !m.as_str().is_empty());
+                            if let Some(m) = m {
+                                let j = i + m.end();
+                                let advanced = EarleyState {
+                                    lhs: state.lhs.clone(),
+                                    alt: state.alt,
+                                    dot: state.dot + 1,
+                                    origin: state.origin,
+                                };
+                                if seen[j].insert(advanced.clone()) {
+                                    chart[j].push(advanced);
+                                }
+                            }
+                        }
+                    }
+                }
+                idx += 1;
+            }
+        }
+
+        let accepted = chart[n].iter().any(|s| {
+            s.lhs == self.start
+                && s.origin == 0
+                && s.dot == self.productions[&s.lhs][s.alt].len()
+        });
+        (accepted, ambiguous)
+    }
+}
+
+fn fresh_name(counter: &mut usize) -> String {
+    let name = format!("__anon_{}", counter);
+    *counter += 1;
+    name
+}
+
+fn flatten_alts(
+    body: &RuleBody,
+    productions: &mut HashMap<String, Vec<Vec<Symbol>>>,
+    counter: &mut usize,
+) -> Vec<Vec<Symbol>> {
+    match body {
+        RuleBody::Choice { members } => members
+            .iter()
+            .flat_map(|m| flatten_alts(m, productions, counter))
+            .collect(),
+        RuleBody::Seq { members } => {
+            let mut seqs = vec![Vec::new()];
+            for m in members {
+                let alts = flatten_alts(m, productions, counter);
+                let mut next = Vec::new();
+                for prefix in &seqs {
+                    for alt in &alts {
+                        let mut combined = prefix.clone();
+                        combined.extend(alt.clone());
+                        next.push(combined);
+                    }
+                }
+                seqs = next;
+            }
+            seqs
+        }
+        RuleBody::Repeat { content } => {
+            let inner_alts = flatten_alts(content, productions, counter);
+            let name = fresh_name(counter);
+            let mut self_alts = vec![Vec::new()];
+            for a in &inner_alts {
+                let mut seq = a.clone();
+                seq.push(Symbol::NonTerminal(name.clone()));
+                self_alts.push(seq);
+            }
+            productions.insert(name.clone(), self_alts);
+            vec![vec![Symbol::NonTerminal(name)]]
+        }
+        RuleBody::Repeat1 { content } => {
+            let inner_alts = flatten_alts(content, productions, counter);
+            let name = fresh_name(counter);
+            let mut self_alts = Vec::new();
+            for a in &inner_alts {
+                self_alts.push(a.clone());
+                let mut seq = a.clone();
+                seq.push(Symbol::NonTerminal(name.clone()));
+                self_alts.push(seq);
+            }
+            productions.insert(name.clone(), self_alts);
+            vec![vec![Symbol::NonTerminal(name)]]
+        }
+        RuleBody::Optional { content } => {
+            let mut alts = flatten_alts(content, productions, counter);
+            alts.push(Vec::new());
+            alts
+        }
+        RuleBody::PrecLeft { content }
+        | RuleBody::PrecRight { content }
+        | RuleBody::Token { content }
+        | RuleBody::ImmediateToken { content }
+        | RuleBody::Field { content, .. }
+        | RuleBody::Alias { content, .. } => flatten_alts(content, productions, counter),
+        RuleBody::Symbol { name } => vec![vec![Symbol::NonTerminal(name.clone())]],
+        RuleBody::String { value } => vec![vec![Symbol::Literal(value.clone())]],
+        RuleBody::Pattern { value } => vec![vec![Symbol::Pattern(value.clone())]],
+    }
+}
+
+pub fn validate(g: &Grammar, input: &str) -> ValidationReport {
+    let compiled = CompiledGrammar::compile(g);
+    let reachable = compiled.reachable();
+    let nullable = compiled.nullable();
+    let (accepted, ambiguous) = compiled.recognize(input);
+    let unused = compiled
+        .productions
+        .keys()
+        .filter(|name| !name.starts_with("__anon_") && !reachable.contains(*name))
+        .cloned()
+        .collect();
+    ValidationReport {
+        accepted,
+        reachable: reachable
+            .into_iter()
+            .filter(|name| !name.starts_with("__anon_"))
+            .collect(),
+        unused,
+        nullable: nullable
+            .into_iter()
+            .filter(|name| !name.starts_with("__anon_"))
+            .collect(),
+        ambiguous: ambiguous.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grammar::Grammar;
+
+    #[test]
+    fn recognizes_left_recursion_and_pattern_terminals() {
+        // expr -> expr "+" num | num
+        // num  -> PATTERN "[0-9]+"
+        let expr = RuleBody::Choice {
+            members: vec![
+                RuleBody::Seq {
+                    members: vec![
+                        RuleBody::Symbol {
+                            name: "expr".to_owned(),
+                        },
+                        RuleBody::String {
+                            value: "+".to_owned(),
+                        },
+                        RuleBody::Symbol {
+                            name: "num".to_owned(),
+                        },
+                    ],
+                },
+                RuleBody::Symbol {
+                    name: "num".to_owned(),
+                },
+            ],
+        };
+        let num = RuleBody::Pattern {
+            value: "[0-9]+".to_owned(),
+        };
+        let g = Grammar::from_rules(vec![("expr", expr), ("num", num)]);
+
+        assert!(validate(&g, "1+2+3").accepted);
+        assert!(!validate(&g, "1+").accepted);
+        assert!(!validate(&g, "1+a").accepted);
+    }
+
+    #[test]
+    fn detects_ambiguity_from_duplicate_alternatives() {
+        let start = RuleBody::Choice {
+            members: vec![
+                RuleBody::String {
+                    value: "a".to_owned(),
+                },
+                RuleBody::String {
+                    value: "a".to_owned(),
+                },
+            ],
+        };
+        let g = Grammar::from_rules(vec![("start", start)]);
+
+        let report = validate(&g, "a");
+        assert!(report.accepted);
+        assert_eq!(report.ambiguous, vec!["start".to_owned()]);
+    }
+
+    #[test]
+    fn reports_unreachable_rules_relative_to_the_first_declared_rule() {
+        let start = RuleBody::String {
+            value: "a".to_owned(),
+        };
+        let other = RuleBody::String {
+            value: "z".to_owned(),
+        };
+        let g = Grammar::from_rules(vec![("start", start), ("other", other)]);
+
+        let report = validate(&g, "a");
+        assert_eq!(report.unused, vec!["other".to_owned()]);
+    }
+}
@@ -4,6 +4,7 @@ use std::{
     path::Path,
 };
 
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 use crate::name_gen::NameGen;
@@ -11,7 +12,10 @@ use crate::name_gen::NameGen;
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RawGrammar {
     pub name: String,
-    rules: HashMap<String, RuleBody>,
+    // An `IndexMap` (rather than a `HashMap`) so the rules come back out in
+    // declaration order: earley.rs treats the first rule as the grammar's
+    // start symbol, matching tree-sitter's own convention.
+    rules: IndexMap<String, RuleBody>,
     extras: Vec<RuleBody>,
 }
 
@@ -53,6 +57,21 @@ impl Grammar {
     pub fn get_rules(&self) -> impl Iterator<Item = &Rule> {
         self.rules.iter()
     }
+
+    #[cfg(test)]
+    pub fn from_rules(rules: Vec<(&str, RuleBody)>) -> Self {
+        Self {
+            name: "test".to_owned(),
+            rules: rules
+                .into_iter()
+                .map(|(name, body)| Rule {
+                    name: name.to_owned(),
+                    body,
+                    is_extra: false,
+                })
+                .collect(),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -67,10 +86,16 @@ pub struct Rule {
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RuleBody {
     Repeat { content: Box<RuleBody> },
+    Repeat1 { content: Box<RuleBody> },
+    Optional { content: Box<RuleBody> },
     Choice { members: Vec<RuleBody> },
     Seq { members: Vec<RuleBody> },
     PrecLeft { content: Box<RuleBody> },
     PrecRight { content: Box<RuleBody> },
+    Token { content: Box<RuleBody> },
+    ImmediateToken { content: Box<RuleBody> },
+    Alias { content: Box<RuleBody>, named: bool, value: String },
+    Field { name: String, content: Box<RuleBody> },
     Symbol { name: String },
     String { value: String },
     Pattern { value: String },
@@ -79,23 +104,20 @@ pub enum RuleBody {
 impl RuleBody {
     pub fn get_nonterminals(&self) -> Vec<String> {
         match self {
+            RuleBody::Symbol { name } => vec![name.clone()],
             RuleBody::Repeat { content }
+            | RuleBody::Repeat1 { content }
+            | RuleBody::Optional { content }
             | RuleBody::PrecLeft { content }
-            | RuleBody::PrecRight { content } => {
-                if let RuleBody::Symbol { name } = &**content {
-                    vec![name.clone()]
-                } else {
-                    vec![]
-                }
+            | RuleBody::PrecRight { content }
+            | RuleBody::Token { content }
+            | RuleBody::ImmediateToken { content }
+            | RuleBody::Alias { content, .. }
+            | RuleBody::Field { content, .. } => content.get_nonterminals(),
+            RuleBody::Choice { members } | RuleBody::Seq { members } => {
+                members.iter().flat_map(|b| b.get_nonterminals()).collect()
             }
-            RuleBody::Choice { members } | RuleBody::Seq { members } => members
-                .iter()
-                .filter_map(|b| match b {
-                    RuleBody::Symbol { name } => Some(name.clone()),
-                    _ => None,
-                })
-                .collect(),
-            _ => vec![],
+            RuleBody::String { .. } | RuleBody::Pattern { .. } => vec![],
         }
     }
 
@@ -113,6 +135,67 @@ impl RuleBody {
                     data,
                 )
             }
+            RuleBody::Repeat1 { content } => {
+                let (new_content, data) = f(&[*content.clone()]);
+                (
+                    RuleBody::Repeat1 {
+                        content: Box::new(new_content[0].clone()),
+                    },
+                    data,
+                )
+            }
+            RuleBody::Optional { content } => {
+                let (new_content, data) = f(&[*content.clone()]);
+                (
+                    RuleBody::Optional {
+                        content: Box::new(new_content[0].clone()),
+                    },
+                    data,
+                )
+            }
+            RuleBody::Token { content } => {
+                let (new_content, data) = f(&[*content.clone()]);
+                (
+                    RuleBody::Token {
+                        content: Box::new(new_content[0].clone()),
+                    },
+                    data,
+                )
+            }
+            RuleBody::ImmediateToken { content } => {
+                let (new_content, data) = f(&[*content.clone()]);
+                (
+                    RuleBody::ImmediateToken {
+                        content: Box::new(new_content[0].clone()),
+                    },
+                    data,
+                )
+            }
+            RuleBody::Alias {
+                content,
+                named,
+                value,
+            } => {
+                let (new_content, data) = f(&[*content.clone()]);
+                (
+                    RuleBody::Alias {
+                        content: Box::new(new_content[0].clone()),
+                        named: *named,
+                        value: value.clone(),
+                    },
+                    data,
+                )
+            }
+            RuleBody::Field { name, content } => {
+                let (new_content, data) = f(&[*content.clone()]);
+                (
+                    RuleBody::Field {
+                        name: name.clone(),
+                        content: Box::new(new_content[0].clone()),
+                    },
+                    data,
+                )
+            }
             RuleBody::Choice { members } => {
                 let (new_members, data) = f(&members[..]);
                 (
@@ -145,22 +228,87 @@ impl RuleBody {
         gen: &mut NameGen,
     ) -> (RuleBody, Vec<(String, RuleBody)>)
     where
-        P: Fn(&RuleBody) -> bool,
+        P: Fn(&RuleBody) -> bool + Copy,
     {
         self.map_subexps(|rules| {
             let mut subexps = Vec::new();
             let mut new_rules = Vec::new();
             for r in rules {
-                let new_r = if pred(r) {
+                // Hoist inside `r` first, so a subexpression buried under a wrapper
+                // like OPTIONAL/REPEAT1/TOKEN (not just a direct Seq/Choice member)
+                // still gets lifted out before `pred` is tested against `r` itself.
+                let (recursed, nested) = r.hoist_subexps(name, pred, gen);
+                subexps.extend(nested);
+                let new_r = if pred(&recursed) {
                     let fresh_name = gen.get_fresh_name(name);
-                    subexps.push((fresh_name.clone(), r.clone()));
+                    subexps.push((fresh_name.clone(), recursed));
                     RuleBody::Symbol { name: fresh_name }
                 } else {
-                    r.clone()
+                    recursed
                 };
                 new_rules.push(new_r);
             }
             (new_rules, subexps)
         })
     }
+
+    /// Rewrites every `Symbol` reference whose name is a key in `renames` to the
+    /// renamed name, leaving all other structure untouched. Used to propagate an
+    /// ALIASed rule's display name to the rest of the grammar that refers to it.
+    pub fn rename_symbols(&self, renames: &HashMap<String, String>) -> RuleBody {
+        if let RuleBody::Symbol { name } = self {
+            if let Some(renamed) = renames.get(name) {
+                return RuleBody::Symbol {
+                    name: renamed.clone(),
+                };
+            }
+        }
+        match self {
+            RuleBody::PrecLeft { content } => {
+                return RuleBody::PrecLeft {
+                    content: Box::new(content.rename_symbols(renames)),
+                };
+            }
+            RuleBody::PrecRight { content } => {
+                return RuleBody::PrecRight {
+                    content: Box::new(content.rename_symbols(renames)),
+                };
+            }
+            _ => {}
+        }
+        self.map_subexps(|rules| {
+            (
+                rules.iter().map(|r| r.rename_symbols(renames)).collect(),
+                (),
+            )
+        })
+        .0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_nonterminals_recurses_through_nested_seq_and_choice() {
+        // choice(seq("(", $.b, ")"), $.lit) nested two levels below a repeat
+        let body = RuleBody::Repeat {
+            content: Box::new(RuleBody::Choice {
+                members: vec![
+                    RuleBody::Seq {
+                        members: vec![
+                            RuleBody::String { value: "(".to_owned() },
+                            RuleBody::Symbol { name: "b".to_owned() },
+                            RuleBody::String { value: ")".to_owned() },
+                        ],
+                    },
+                    RuleBody::Symbol { name: "lit".to_owned() },
+                ],
+            }),
+        };
+        let mut nonterminals = body.get_nonterminals();
+        nonterminals.sort();
+        assert_eq!(nonterminals, vec!["b".to_owned(), "lit".to_owned()]);
+    }
 }
@@ -1,23 +1,43 @@
 use std::env;
 
 mod ast_types;
+mod backend;
+mod earley;
 mod grammar;
 mod graph;
 mod name_gen;
 mod type_gen;
 
-use crate::{grammar::Grammar, type_gen::TypeGenerator};
+use crate::{backend::Backend, backend::RustBackend, grammar::Grammar, type_gen::TypeGenerator};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        panic!("usage: {} <path>", args[0]);
+    if args.len() < 2 {
+        panic!("usage: {} <path> [rust | validate <input>]", args[0]);
     }
     let g = Grammar::from_file(&args[1]);
-    let mut ty_gen = TypeGenerator::new();
+    if args.get(2).map(String::as_str) == Some("validate") {
+        let input = args.get(3).cloned().unwrap_or_default();
+        let report = earley::validate(&g, &input);
+        println!("accepted: {}", report.accepted);
+        println!("reachable: {:?}", report.reachable);
+        println!("unused: {:?}", report.unused);
+        println!("nullable: {:?}", report.nullable);
+        println!("ambiguous: {:?}", report.ambiguous);
+        return;
+    }
+    let mut ty_gen = TypeGenerator::new(&g);
     for r in g.get_rules() {
         ty_gen.add_rule(r);
     }
-    let tys = ty_gen.gen();
-    ast_types::print_type_hierarchy(&tys);
+    let result = ty_gen.gen();
+    match args.get(2).map(String::as_str) {
+        Some("rust") => {
+            let backend = RustBackend::new();
+            println!("{}", backend.emit(&result.typedefs, &result.groups));
+            println!();
+            println!("{}", backend.emit_parsers(&result.functiondefs, &result.groups));
+        }
+        _ => ast_types::print_type_hierarchy(&result.typedefs),
+    }
 }
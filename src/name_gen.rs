@@ -1,15 +1,56 @@
+use std::collections::{HashMap, HashSet};
+
 pub struct NameGen {
-    idx: usize,
+    counters: HashMap<String, usize>,
+    reserved: HashSet<String>,
 }
 
 impl NameGen {
     pub fn new() -> Self {
-        Self { idx: 0 }
+        Self {
+            counters: HashMap::new(),
+            reserved: HashSet::new(),
+        }
+    }
+
+    pub fn with_reserved_names<I>(names: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        Self {
+            counters: HashMap::new(),
+            reserved: names.into_iter().collect(),
+        }
     }
 
     pub fn get_fresh_name(&mut self, prefix: &str) -> String {
-        let name = format!("{}_{}", prefix, self.idx);
-        self.idx += 1;
-        name
+        loop {
+            let idx = self.counters.entry(prefix.to_owned()).or_insert(0);
+            let candidate = format!("{}_{}", prefix, idx);
+            *idx += 1;
+            if self.reserved.insert(candidate.clone()) {
+                return candidate;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_fresh_name_skips_names_already_reserved() {
+        let mut gen = NameGen::with_reserved_names(vec!["expr_0".to_owned()]);
+
+        assert_eq!(gen.get_fresh_name("expr"), "expr_1");
+    }
+
+    #[test]
+    fn get_fresh_name_never_repeats_across_calls() {
+        let mut gen = NameGen::new();
+        let names: HashSet<String> = (0..5).map(|_| gen.get_fresh_name("x")).collect();
+
+        assert_eq!(names.len(), 5);
     }
 }
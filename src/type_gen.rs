@@ -2,33 +2,49 @@ use std::collections::{HashMap, VecDeque};
 
 use crate::{
     ast_types::AstType,
-    grammar::{Rule, RuleBody},
+    grammar::{Grammar, Rule, RuleBody},
     graph::{self, Graph, VertexId},
     name_gen::NameGen,
 };
 
+pub struct ParserDef {
+    pub name: String,
+    pub body: RuleBody,
+}
+
+pub struct GenResult {
+    pub typedefs: Vec<AstType>,
+    pub functiondefs: Vec<ParserDef>,
+    pub groups: Vec<Vec<String>>,
+}
+
 pub struct TypeGenerator {
-    graph: Graph<String>,
+    graph: Graph<String, ()>,
     vertex_map: HashMap<String, VertexId>,
     rules: HashMap<String, RuleBody>,
     extras: Vec<Rule>,
     name_gen: NameGen,
+    // Rule name -> ALIAS value, for rules whose body is a top-level ALIAS. The
+    // alias names the type this rule generates, not whatever the rule itself
+    // is called.
+    renames: HashMap<String, String>,
 }
 
 impl TypeGenerator {
-    pub fn new() -> Self {
+    pub fn new(g: &Grammar) -> Self {
         Self {
             graph: Graph::new(),
             vertex_map: HashMap::new(),
             rules: HashMap::new(),
             extras: Vec::new(),
-            name_gen: NameGen::new(),
+            name_gen: NameGen::with_reserved_names(g.get_rules().map(|r| r.name.clone())),
+            renames: HashMap::new(),
         }
     }
 
     fn get_or_insert_vertex(&mut self, name: &str) -> VertexId {
         if !self.vertex_map.contains_key(name) {
-            let id = self.graph.add_vertex(name.to_owned());
+            let id = self.graph.add_vertex(name.to_owned(), ());
             self.vertex_map.insert(name.to_owned(), id);
         }
         *self.vertex_map.get(name).unwrap()
@@ -46,35 +62,71 @@ impl TypeGenerator {
             for (fresh_name, sub_exp) in sub_exps {
                 next.push_back((fresh_name.to_owned(), sub_exp.clone()));
             }
+            if let RuleBody::Alias { value, .. } = &new_body {
+                self.renames.insert(next_name.clone(), value.clone());
+            }
             if rule.is_extra {
                 self.extras.push(rule.clone());
             } else {
-                self.add_to_dag(&rule.name, &next_name, &new_body.get_nonterminals())
+                self.add_to_dag(&next_name, &new_body.get_nonterminals())
             }
             self.rules.insert(next_name, new_body);
         }
     }
 
-    fn add_to_dag(&mut self, rule_name: &str, name: &str, nonterminals: &[String]) {
+    fn add_to_dag(&mut self, name: &str, nonterminals: &[String]) {
         let uid = self.get_or_insert_vertex(name);
         for sym_name in nonterminals {
-            if sym_name != rule_name {
+            if sym_name != name {
                 let vid = self.get_or_insert_vertex(sym_name);
                 self.graph.add_edge(uid, vid);
             }
         }
     }
 
-    pub fn gen(&self) -> Vec<AstType> {
-        let order = graph::topo_sort(&self.graph);
+    fn display_name(&self, name: &str) -> String {
+        self.renames
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_owned())
+    }
+
+    pub fn gen(&self) -> GenResult {
+        let components = graph::scc_topo_order(&self.graph);
         let mut bodies = Vec::new();
-        for s in order {
-            bodies.push((s, self.rules.get(s).unwrap()));
+        let mut groups = Vec::new();
+        for component in &components {
+            let mut group = Vec::new();
+            for vid in component {
+                let name = self.graph.get_vertex(*vid).value;
+                let display_name = self.display_name(name);
+                let body = self.rules.get(name).unwrap().rename_symbols(&self.renames);
+                bodies.push((display_name.clone(), body));
+                group.push(display_name);
+            }
+            groups.push(group);
         }
-        bodies.extend(self.extras.iter().map(|r| (&r.name, &r.body)));
-        bodies
+        bodies.extend(self.extras.iter().map(|r| {
+            (
+                self.display_name(&r.name),
+                r.body.rename_symbols(&self.renames),
+            )
+        }));
+        let typedefs = bodies
             .iter()
             .map(|(name, body)| AstType::from_rule(name, body))
-            .collect()
+            .collect();
+        let functiondefs = bodies
+            .iter()
+            .map(|(name, body)| ParserDef {
+                name: name.clone(),
+                body: body.clone(),
+            })
+            .collect();
+        GenResult {
+            typedefs,
+            functiondefs,
+            groups,
+        }
     }
 }